@@ -0,0 +1,82 @@
+use std::fmt;
+
+/// Everything that can go wrong while decoding an MLG file.
+///
+/// Parsing never panics: malformed input is reported through this type so a
+/// single corrupt block can be surfaced with a precise byte offset instead of
+/// aborting the whole process.
+#[derive(Debug)]
+pub enum ParseError {
+    UnexpectedEof {
+        offset: usize,
+        needed: usize,
+    },
+    UnsupportedFieldType(i8),
+    UnsupportedBlockType(i8),
+    BadUtf8 {
+        offset: usize,
+    },
+    UnsupportedFormat(String),
+    Message(String),
+    Context {
+        message: String,
+        source: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Builds an error from a plain, static or owned message.
+    pub fn msg(message: impl Into<String>) -> Self {
+        ParseError::Message(message.into())
+    }
+
+    /// Wraps this error with a note about what was being decoded, e.g.
+    /// `err.context("compound key (field `x`)")`.
+    pub fn context(self, message: impl Into<String>) -> Self {
+        ParseError::Context {
+            message: message.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEof { offset, needed } => write!(
+                f,
+                "unexpected end of file at offset {offset}: needed {needed} more byte(s)"
+            ),
+            ParseError::UnsupportedFieldType(field_type) => {
+                write!(f, "unsupported field type: {field_type}")
+            }
+            ParseError::UnsupportedBlockType(block_type) => {
+                write!(f, "unsupported block type: {block_type}")
+            }
+            ParseError::BadUtf8 { offset } => write!(f, "invalid utf-8 at offset {offset}"),
+            ParseError::UnsupportedFormat(format) => write!(f, "unsupported format: {format}"),
+            ParseError::Message(message) => write!(f, "{message}"),
+            ParseError::Context { message, source } => write!(f, "{message}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> Self {
+        ParseError::msg(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(e: serde_json::Error) -> Self {
+        ParseError::msg(e.to_string())
+    }
+}
+
+impl From<csv::Error> for ParseError {
+    fn from(e: csv::Error) -> Self {
+        ParseError::msg(e.to_string())
+    }
+}
@@ -1,4 +1,7 @@
+mod error;
 mod parser;
+mod reader;
+mod writer;
 
 use clap::{arg, Command};
 use std::path::PathBuf;
@@ -13,10 +16,12 @@ fn cli() -> Command {
             Command::new("convert")
                 .about("Converts MLG file to another format")
                 .arg_required_else_help(true)
-                .arg(arg!(<FORMAT> "Target format, one of: [csv, json]"))
+                .arg(arg!(<FORMAT> "Target format, one of: [csv, json, cbor, mlg]"))
                 .arg(
                     arg!(<PATH> ... "Files to convert").value_parser(clap::value_parser!(PathBuf)),
-                ),
+                )
+                .arg(arg!(--streaming "Stream blocks straight to the output writer instead of accumulating every decoded block in memory"))
+                .arg(arg!(--compress "Compress the generated csv, json or cbor output with zlib (mlg output is not compressed)")),
         )
 }
 
@@ -35,13 +40,21 @@ fn main() {
                 .into_iter()
                 .flatten()
                 .collect::<Vec<_>>();
+            let streaming = sub_matches.get_flag("streaming");
+            let compress = sub_matches.get_flag("compress");
 
             match format {
                 "csv" => {
-                    parser::parse(paths, parser::Formats::Csv);
+                    parser::parse(paths, parser::Formats::Csv, streaming, compress);
                 }
                 "json" => {
-                    parser::parse(paths, parser::Formats::Json);
+                    parser::parse(paths, parser::Formats::Json, streaming, compress);
+                }
+                "cbor" => {
+                    parser::parse(paths, parser::Formats::Cbor, streaming, compress);
+                }
+                "mlg" => {
+                    parser::convert_to_mlg(paths);
                 }
                 _ => {
                     println!("Invalid format: {}", format);
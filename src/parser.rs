@@ -1,41 +1,47 @@
+use crate::error::ParseError;
+use crate::reader::Reader;
 use csv::WriterBuilder;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 use hashbrown::HashMap;
 use serde::{
-    ser::{SerializeMap},
-    Serialize, Serializer,
+    de::{self, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
 };
 use std::{
+    fmt,
     fs::File,
-    io::{LineWriter, Read, Write},
+    io::{BufRead, LineWriter, Read, Write},
     path::PathBuf,
-    str, usize,
 };
 
-const FORMAT_LENGTH: usize = 6;
+pub(crate) const FORMAT_LENGTH: usize = 6;
 const LOGGER_FIELD_LENGTH: i16 = 55;
-const FIELD_NAME_LENGTH: usize = 34;
-const FIELD_UNITS_LENGTH: usize = 10;
-const MARKER_MESSAGE_LENGTH: usize = 50;
-const TYPE_FIELD: &str = "field";
-const TYPE_MARKER: &str = "marker";
-const BLOCK_TYPE_FIELD: i8 = 0;
-const BLOCK_TYPE_MARKER: i8 = 1;
+pub(crate) const FIELD_NAME_LENGTH: usize = 34;
+pub(crate) const FIELD_UNITS_LENGTH: usize = 10;
+pub(crate) const MARKER_MESSAGE_LENGTH: usize = 50;
+pub(crate) const TYPE_FIELD: &str = "field";
+pub(crate) const TYPE_MARKER: &str = "marker";
+pub(crate) const BLOCK_TYPE_FIELD: i8 = 0;
+pub(crate) const BLOCK_TYPE_MARKER: i8 = 1;
 const FIELD_DISPLAY_STYLE_FLOAT: &str = "Float";
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Parsed {
-    file_format: String,
-    format_version: i16,
-    timestamp: i32,
-    info_data_start: i16,
-    data_begin_index: i32,
-    record_length: i16,
-    num_logger_fields: i16,
-    fields: Vec<LoggerFieldScalar>,
-    bit_field_names: String,
-    info_data: String,
-    data_blocks: Vec<DataBlock>,
+pub(crate) struct Parsed {
+    pub(crate) file_format: String,
+    pub(crate) format_version: i16,
+    pub(crate) timestamp: i32,
+    pub(crate) info_data_start: i16,
+    pub(crate) data_begin_index: i32,
+    pub(crate) record_length: i16,
+    pub(crate) num_logger_fields: i16,
+    pub(crate) fields: Vec<LoggerFieldScalar>,
+    pub(crate) bit_field_names: String,
+    pub(crate) info_data: String,
+    pub(crate) data_blocks: Vec<DataBlock>,
 }
 
 #[derive(Debug, Serialize)]
@@ -46,7 +52,7 @@ struct DataBlockField {
     timestamp: u16,
 }
 
-type Records = HashMap<String, f64>;
+pub(crate) type Records = HashMap<String, f64>;
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -56,25 +62,25 @@ struct BlockHeader {
     timestamp: u16,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct LoggerFieldScalar {
-    field_type: i8,
-    name: String,
-    units: String,
-    display_style: String,
-    scale: f32,
-    transform: f32,
-    digits: i8,
+pub(crate) struct LoggerFieldScalar {
+    pub(crate) field_type: i8,
+    pub(crate) name: String,
+    pub(crate) units: String,
+    pub(crate) display_style: String,
+    pub(crate) scale: f32,
+    pub(crate) transform: f32,
+    pub(crate) digits: i8,
 }
 
 #[derive(Debug)]
-struct DataBlock {
-    block_type: i8,
-    counter: i8,
-    timestamp: u16,
-    records: Records,
-    message: String, // marker block
+pub(crate) struct DataBlock {
+    pub(crate) block_type: i8,
+    pub(crate) counter: i8,
+    pub(crate) timestamp: u16,
+    pub(crate) records: Records,
+    pub(crate) message: String, // marker block
 }
 
 impl Serialize for DataBlock {
@@ -82,16 +88,19 @@ impl Serialize for DataBlock {
     where
         S: Serializer,
     {
-        let mut map = serializer.serialize_map(Some(self.records.len() + 2))?;
+        let mut map = serializer.serialize_map(Some(self.records.len() + 3))?;
 
         // serialize normal fields
         map.serialize_entry(&"timestamp", &self.timestamp)?;
+        map.serialize_entry(&"counter", &self.counter)?;
         map.serialize_entry(
             &"type",
             match self.block_type {
                 BLOCK_TYPE_FIELD => TYPE_FIELD,
                 BLOCK_TYPE_MARKER => TYPE_MARKER,
-                _ => panic!("Unsupported Block Type"),
+                // decode already rejects any other block_type via ParseError,
+                // so DataBlock can never be constructed with one here
+                _ => unreachable!("Unsupported Block Type"),
             },
         )?;
 
@@ -111,177 +120,557 @@ impl Serialize for DataBlock {
     }
 }
 
+impl<'de> Deserialize<'de> for DataBlock {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct DataBlockVisitor;
+
+        impl<'de> Visitor<'de> for DataBlockVisitor {
+            type Value = DataBlock;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a data block map with `timestamp` and `type` keys")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<DataBlock, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut timestamp = None;
+                let mut counter = 0;
+                let mut block_type = None;
+                let mut message = String::new();
+                let mut records = Records::new();
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "timestamp" => timestamp = Some(map.next_value()?),
+                        "counter" => counter = map.next_value()?,
+                        "type" => {
+                            let raw: String = map.next_value()?;
+                            block_type = Some(match raw.as_str() {
+                                TYPE_FIELD => BLOCK_TYPE_FIELD,
+                                TYPE_MARKER => BLOCK_TYPE_MARKER,
+                                other => {
+                                    return Err(de::Error::custom(format!(
+                                        "unsupported block type: {other}"
+                                    )))
+                                }
+                            });
+                        }
+                        "message" => message = map.next_value()?,
+                        _ => {
+                            records.insert(key, map.next_value()?);
+                        }
+                    }
+                }
+
+                Ok(DataBlock {
+                    block_type: block_type.ok_or_else(|| de::Error::missing_field("type"))?,
+                    counter,
+                    timestamp: timestamp.ok_or_else(|| de::Error::missing_field("timestamp"))?,
+                    records,
+                    message,
+                })
+            }
+        }
+
+        deserializer.deserialize_map(DataBlockVisitor)
+    }
+}
+
 pub enum Formats {
     Csv,
     Json,
+    Cbor,
+}
+
+/// An output file, optionally wrapped in zlib compression. Lets every output
+/// path (`save_csv`, the json/cbor branches of `parse`, the streaming
+/// writers) share one `--compress` code path instead of each picking an
+/// encoder type.
+enum OutputWriter {
+    Plain(File),
+    Compressed(ZlibEncoder<File>),
 }
 
-pub fn parse(paths: Vec<&PathBuf>, format: Formats) {
+impl OutputWriter {
+    fn new(file: File, compress: bool) -> Self {
+        if compress {
+            OutputWriter::Compressed(ZlibEncoder::new(file, Compression::default()))
+        } else {
+            OutputWriter::Plain(file)
+        }
+    }
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputWriter::Plain(w) => w.write(buf),
+            OutputWriter::Compressed(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputWriter::Plain(w) => w.flush(),
+            OutputWriter::Compressed(w) => w.flush(),
+        }
+    }
+}
+
+/// How often, in blocks, streaming conversions print a progress update.
+const PROGRESS_INTERVAL: usize = 100_000;
+
+pub fn parse(paths: Vec<&PathBuf>, format: Formats, streaming: bool, compress: bool) {
     for path in paths {
+        if streaming {
+            match &format {
+                Formats::Csv | Formats::Json => {
+                    if let Err(e) = parse_single_file_streaming(path, &format, compress) {
+                        println!("Error in [{}]: {}", path.display(), e);
+                    }
+                    continue;
+                }
+                Formats::Cbor => {
+                    println!(
+                        "--streaming isn't supported for cbor yet, falling back to buffered conversion"
+                    );
+                }
+            }
+        }
+
         let parsed = parse_single_file(path);
 
-        match &parsed {
-            Ok(_) => {}
-            Err(e) => return println!("Error in [{}]: {}", path.display(), e),
+        if let Err(e) = &parsed {
+            println!("Error in [{}]: {}", path.display(), e);
+            continue;
         }
 
         match format {
             Formats::Csv => {
                 let filepath = path.with_extension("csv");
-                save_csv(&parsed.unwrap(), &filepath);
+                save_csv(&parsed.unwrap(), &filepath, compress);
                 println!("Generated: {}", filepath.display());
             }
             Formats::Json => {
-                let json = serde_json::to_string(&parsed).expect("Unable to serialize the result");
+                let json = serde_json::to_string(&parsed.unwrap())
+                    .expect("Unable to serialize the result");
                 let filepath = path.with_extension("json");
-                File::create(&filepath)
-                    .unwrap()
+                let file = File::create(&filepath).expect("Unable to create output file");
+                OutputWriter::new(file, compress)
                     .write_all(json.as_bytes())
                     .expect("Unable to save output file");
                 println!("Generated: {}", &filepath.display());
             }
+            Formats::Cbor => {
+                let filepath = path.with_extension("cbor");
+                let file = File::create(&filepath).expect("Unable to create output file");
+                serde_cbor::to_writer(OutputWriter::new(file, compress), &parsed.unwrap())
+                    .expect("Unable to serialize the result");
+                println!("Generated: {}", &filepath.display());
+            }
         }
     }
 }
 
-fn parse_single_file(path: &PathBuf) -> Result<Parsed, &str> {
-    let mut file = File::open(path).expect("Unable to open file");
+/// Reads `.json` files back into a `Parsed` value and re-emits them as
+/// `.mlg`, the inverse of `Formats::Json`.
+pub fn convert_to_mlg(paths: Vec<&PathBuf>) {
+    for path in paths {
+        if let Err(e) = convert_single_file_to_mlg(path) {
+            println!("Error in [{}]: {}", path.display(), e);
+        }
+    }
+}
+
+fn convert_single_file_to_mlg(path: &PathBuf) -> Result<(), ParseError> {
+    let json =
+        std::fs::read_to_string(path).map_err(|e| ParseError::from(e).context("reading file"))?;
+    let parsed: Parsed = serde_json::from_str(&json).map_err(|e| ParseError::msg(e.to_string()))?;
+
+    let filepath = path.with_extension("mlg");
+    crate::writer::save_mlg(&parsed, &filepath)?;
+    println!("Generated: {}", filepath.display());
+
+    Ok(())
+}
+
+/// Everything that precedes the data blocks: fixed header fields plus the
+/// logger field table. Kept resident for the lifetime of a parse; only the
+/// (potentially huge) data blocks are handled a block at a time.
+struct Header {
+    file_format: String,
+    format_version: i16,
+    timestamp: i32,
+    info_data_start: i16,
+    data_begin_index: i32,
+    record_length: i16,
+    num_logger_fields: i16,
+    fields: Vec<LoggerFieldScalar>,
+    bit_field_names: String,
+    info_data: String,
+}
+
+/// Reads `path`, transparently inflating a gzip- or zlib-wrapped file
+/// (detected by its magic header) straight into the returned buffer. The
+/// compressed bytes are never materialized as a separate `Vec` — only the
+/// raw `File`'s internal `BufReader` page and the final decoded buffer are
+/// resident at once.
+fn read_file(path: &PathBuf) -> Result<Vec<u8>, ParseError> {
+    let file = File::open(path).map_err(|e| ParseError::from(e).context("opening file"))?;
+    let mut reader = std::io::BufReader::new(file);
+    let magic = reader
+        .fill_buf()
+        .map_err(|e| ParseError::from(e).context("reading file"))?;
+
     let mut buff = Vec::new();
-    let mut offset: usize = 0;
-
-    file.read_to_end(&mut buff).expect("Unable to read file");
-
-    let mut result = Parsed {
-        file_format: "".to_string(),
-        format_version: 0,
-        timestamp: 0,
-        info_data_start: 0,
-        data_begin_index: 0,
-        record_length: 0,
-        num_logger_fields: 0,
-        fields: Vec::new(),
-        bit_field_names: "".to_string(),
-        info_data: "".to_string(),
-        data_blocks: Vec::new(),
-    };
+    match magic.get(0..2) {
+        Some([0x1f, 0x8b]) => {
+            GzDecoder::new(reader)
+                .read_to_end(&mut buff)
+                .map_err(|e| ParseError::from(e).context("decompressing gzip input"))?;
+        }
+        Some([0x78, _]) => {
+            ZlibDecoder::new(reader)
+                .read_to_end(&mut buff)
+                .map_err(|e| ParseError::from(e).context("decompressing zlib input"))?;
+        }
+        _ => {
+            reader
+                .read_to_end(&mut buff)
+                .map_err(|e| ParseError::from(e).context("reading file"))?;
+        }
+    }
 
-    result.file_format = parse_string(&buff, &mut offset, FORMAT_LENGTH);
-
-    if result.file_format != "MLVLG" {
-      return Err("Unsupported file format");
-  }
-
-  result.format_version = parse_i16(&buff, &mut offset);
-
-  if result.format_version != 1 {
-      return Err("Unsupported file format version");
-  }
-
-    result.timestamp = parse_i32(&buff, &mut offset);
-    result.info_data_start = parse_i16(&buff, &mut offset);
-    result.data_begin_index = parse_i32(&buff, &mut offset);
-    result.record_length = parse_i16(&buff, &mut offset);
-    result.num_logger_fields = parse_i16(&buff, &mut offset);
-
-    let logger_fields_length = offset + (result.num_logger_fields * LOGGER_FIELD_LENGTH) as usize;
-
-    while offset < logger_fields_length {
-        result.fields.push(LoggerFieldScalar {
-            field_type: parse_i8(&buff, &mut offset),
-            name: parse_string(&buff, &mut offset, FIELD_NAME_LENGTH),
-            units: parse_string(&buff, &mut offset, FIELD_UNITS_LENGTH),
-            display_style: match parse_i8(&buff, &mut offset) {
-                0 => "Float".to_string(),
-                1 => "Hex".to_string(),
-                2 => "bits".to_string(),
-                3 => "Date".to_string(),
-                4 => "On/Off".to_string(),
-                5 => "Yes/No".to_string(),
-                6 => "High/Low".to_string(),
-                7 => "Active/Inactive".to_string(),
-                _ => panic!("Unsupported Field Display Style"),
-            },
-            scale: parse_f32(&buff, &mut offset),
-            transform: parse_f32(&buff, &mut offset),
-            digits: parse_i8(&buff, &mut offset),
-        });
+    Ok(buff)
+}
+
+fn parse_header(r: &mut Reader) -> Result<Header, ParseError> {
+    let file_format = r
+        .read_str(FORMAT_LENGTH)
+        .map_err(|e| e.context("file format"))?;
+
+    if file_format != "MLVLG" {
+        return Err(ParseError::UnsupportedFormat(file_format));
+    }
+
+    let format_version = r.read::<i16>().map_err(|e| e.context("format version"))?;
+
+    if format_version != 1 {
+        return Err(ParseError::msg(format!(
+            "unsupported file format version: {}",
+            format_version
+        )));
     }
 
-    result.bit_field_names = parse_string(
-        &buff,
-        &mut offset,
-        result.info_data_start as usize - logger_fields_length,
-    );
-
-    jump(&mut offset, result.info_data_start as usize);
-
-    result.info_data = parse_string(
-        &buff,
-        &mut offset,
-        (result.data_begin_index - result.info_data_start as i32) as usize,
-    );
-
-    jump(&mut offset, result.data_begin_index as usize);
-
-    while offset < buff.len() {
-        // TODO: report progress every X record
-        let mut records: Records = HashMap::new();
-        let header = BlockHeader {
-            block_type: parse_i8(&buff, &mut offset),
-            counter: parse_i8(&buff, &mut offset),
-            timestamp: parse_u16(&buff, &mut offset),
+    let timestamp = r.read::<i32>().map_err(|e| e.context("timestamp"))?;
+    let info_data_start = r.read::<i16>().map_err(|e| e.context("info data start"))?;
+    let data_begin_index = r.read::<i32>().map_err(|e| e.context("data begin index"))?;
+    let record_length = r.read::<i16>().map_err(|e| e.context("record length"))?;
+    let num_logger_fields = r
+        .read::<i16>()
+        .map_err(|e| e.context("num logger fields"))?;
+
+    let logger_fields_length =
+        r.position() + num_logger_fields as usize * LOGGER_FIELD_LENGTH as usize;
+    let mut fields = Vec::new();
+
+    while r.position() < logger_fields_length {
+        let field_type = r.read::<i8>().map_err(|e| e.context("field type"))?;
+        let name = r
+            .read_str(FIELD_NAME_LENGTH)
+            .map_err(|e| e.context("field name"))?;
+        let units = r
+            .read_str(FIELD_UNITS_LENGTH)
+            .map_err(|e| e.context(format!("field units (field `{}`)", name)))?;
+        let display_style_raw = r
+            .read::<i8>()
+            .map_err(|e| e.context("field display style"))?;
+        let display_style = match display_style_raw {
+            0 => "Float".to_string(),
+            1 => "Hex".to_string(),
+            2 => "bits".to_string(),
+            3 => "Date".to_string(),
+            4 => "On/Off".to_string(),
+            5 => "Yes/No".to_string(),
+            6 => "High/Low".to_string(),
+            7 => "Active/Inactive".to_string(),
+            _ => {
+                return Err(ParseError::msg(format!(
+                    "unsupported field display style: {}",
+                    display_style_raw
+                ))
+                .context(format!("field `{}`", name)))
+            }
         };
-        match header.block_type {
-            BLOCK_TYPE_FIELD => {
-                for field in result.fields.iter() {
-                    records.insert(
-                        field.name.to_string(),
-                        match field.field_type {
-                            // Logger Field – scalar
-                            0 => parse_u8(&buff, &mut offset) as f64,
-                            1 => parse_i8(&buff, &mut offset) as f64,
-                            2 => parse_u16(&buff, &mut offset) as f64,
-                            3 => parse_i16(&buff, &mut offset) as f64,
-                            4 => parse_u32(&buff, &mut offset) as f64,
-                            5 => parse_i32(&buff, &mut offset) as f64,
-                            6 => parse_i64(&buff, &mut offset) as f64,
-                            7 => parse_f32(&buff, &mut offset) as f64,
-                            // Logger Field - Bit
-                            10 => parse_u8(&buff, &mut offset) as f64,
-                            11 => parse_u16(&buff, &mut offset) as f64,
-                            12 => parse_u32(&buff, &mut offset) as f64,
-                            _ => panic!("Unsupported Field Type"),
-                        },
-                    );
-                }
 
-                // don't parse "crc" (not needed for now), just advance offset
-                advance(&mut offset, std::mem::size_of::<u8>());
+        fields.push(LoggerFieldScalar {
+            field_type,
+            name,
+            units,
+            display_style,
+            scale: r.read::<f32>().map_err(|e| e.context("field scale"))?,
+            transform: r.read::<f32>().map_err(|e| e.context("field transform"))?,
+            digits: r.read::<i8>().map_err(|e| e.context("field digits"))?,
+        });
+    }
 
-                result.data_blocks.push(DataBlock {
-                    block_type: header.block_type,
-                    counter: header.counter,
-                    timestamp: header.timestamp,
-                    records,
-                    message: "".to_string(),
-                });
+    let bit_field_names_length = (info_data_start as usize)
+        .checked_sub(logger_fields_length)
+        .ok_or_else(|| ParseError::msg("info data start precedes end of logger fields"))?;
+
+    let bit_field_names = r
+        .read_str(bit_field_names_length)
+        .map_err(|e| e.context("bit field names"))?;
+
+    r.seek(info_data_start as usize);
+
+    let info_data_length = data_begin_index
+        .checked_sub(info_data_start as i32)
+        .filter(|len| *len >= 0)
+        .ok_or_else(|| ParseError::msg("data begin index precedes info data start"))?
+        as usize;
+    let info_data = r
+        .read_str(info_data_length)
+        .map_err(|e| e.context("info data"))?;
+
+    r.seek(data_begin_index as usize);
+
+    Ok(Header {
+        file_format,
+        format_version,
+        timestamp,
+        info_data_start,
+        data_begin_index,
+        record_length,
+        num_logger_fields,
+        fields,
+        bit_field_names,
+        info_data,
+    })
+}
+
+/// Decodes the single block at the reader's current position, advancing it
+/// past the block (and, for field blocks, the trailing crc byte the reader
+/// currently skips).
+fn parse_data_block(r: &mut Reader, fields: &[LoggerFieldScalar]) -> Result<DataBlock, ParseError> {
+    let mut records: Records = HashMap::new();
+    let header = BlockHeader {
+        block_type: r.read::<i8>().map_err(|e| e.context("block type"))?,
+        counter: r.read::<i8>().map_err(|e| e.context("block counter"))?,
+        timestamp: r.read::<u16>().map_err(|e| e.context("block timestamp"))?,
+    };
+
+    match header.block_type {
+        BLOCK_TYPE_FIELD => {
+            for field in fields {
+                let value = match field.field_type {
+                    // Logger Field – scalar
+                    0 => r.read::<u8>()? as f64,
+                    1 => r.read::<i8>()? as f64,
+                    2 => r.read::<u16>()? as f64,
+                    3 => r.read::<i16>()? as f64,
+                    4 => r.read::<u32>()? as f64,
+                    5 => r.read::<i32>()? as f64,
+                    6 => r.read::<i64>()? as f64,
+                    7 => r.read::<f32>()? as f64,
+                    // Logger Field - Bit
+                    10 => r.read::<u8>()? as f64,
+                    11 => r.read::<u16>()? as f64,
+                    12 => r.read::<u32>()? as f64,
+                    other => {
+                        return Err(ParseError::UnsupportedFieldType(other)
+                            .context(format!("field `{}`", field.name)))
+                    }
+                };
+                records.insert(field.name.to_string(), value);
             }
-            BLOCK_TYPE_MARKER => result.data_blocks.push(DataBlock {
+
+            // don't parse "crc" (not needed for now), just skip past it
+            r.read::<u8>().map_err(|e| e.context("block crc"))?;
+
+            Ok(DataBlock {
                 block_type: header.block_type,
                 counter: header.counter,
                 timestamp: header.timestamp,
                 records,
-                message: parse_string(&buff, &mut offset, MARKER_MESSAGE_LENGTH),
-            }),
-            _ => panic!("Unsupported Block Type"),
-        };
+                message: "".to_string(),
+            })
+        }
+        BLOCK_TYPE_MARKER => Ok(DataBlock {
+            block_type: header.block_type,
+            counter: header.counter,
+            timestamp: header.timestamp,
+            records,
+            message: r
+                .read_str(MARKER_MESSAGE_LENGTH)
+                .map_err(|e| e.context("marker message"))?,
+        }),
+        other => Err(ParseError::UnsupportedBlockType(other).context("block header")),
+    }
+}
+
+fn parse_single_file(path: &PathBuf) -> Result<Parsed, ParseError> {
+    let buff = read_file(path)?;
+    let mut r = Reader::new(&buff);
+
+    let header = parse_header(&mut r)?;
+
+    let mut data_blocks = Vec::new();
+    while r.remaining() > 0 {
+        data_blocks.push(parse_data_block(&mut r, &header.fields)?);
+    }
+
+    Ok(Parsed {
+        file_format: header.file_format,
+        format_version: header.format_version,
+        timestamp: header.timestamp,
+        info_data_start: header.info_data_start,
+        data_begin_index: header.data_begin_index,
+        record_length: header.record_length,
+        num_logger_fields: header.num_logger_fields,
+        fields: header.fields,
+        bit_field_names: header.bit_field_names,
+        info_data: header.info_data,
+        data_blocks,
+    })
+}
+
+/// Decodes `path` one block at a time and hands each one straight to the
+/// chosen writer, so decoded blocks never pile up in a `Vec<DataBlock>` the
+/// way `parse_single_file` does. This bounds the *decoded* working set to the
+/// header plus the block being written (and, for csv, a single reused row
+/// buffer), not every `DataBlock` — but the input file itself is still read
+/// (and, if compressed, inflated) into one in-memory buffer first, because
+/// `Reader` decodes from a byte slice and `parse_header` seeks to the absolute
+/// offsets stored in the header.
+/// Peak memory is therefore still O(file size), not O(header size); only the
+/// block-accumulation blowup is avoided.
+fn parse_single_file_streaming(
+    path: &PathBuf,
+    format: &Formats,
+    compress: bool,
+) -> Result<(), ParseError> {
+    let buff = read_file(path)?;
+    let mut r = Reader::new(&buff);
+
+    let header = parse_header(&mut r)?;
+
+    match format {
+        Formats::Csv => stream_csv(&header, &mut r, &path.with_extension("csv"), compress),
+        Formats::Json => stream_json(&header, &mut r, &path.with_extension("json"), compress),
+        Formats::Cbor => unreachable!("cbor streaming is handled by the caller"),
+    }
+}
+
+fn stream_csv(
+    header: &Header,
+    r: &mut Reader,
+    path: &PathBuf,
+    compress: bool,
+) -> Result<(), ParseError> {
+    let line_writer = LineWriter::new(OutputWriter::new(File::create(path)?, compress));
+    let mut writer = WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(line_writer);
+
+    writer.write_record(header.fields.iter().map(|field| &field.name))?;
+    writer.write_record(header.fields.iter().map(|field| &field.units))?;
+
+    let mut row: Vec<String> = Vec::with_capacity(header.fields.len());
+    let mut count = 0usize;
+
+    while r.remaining() > 0 {
+        let block = parse_data_block(r, &header.fields)?;
+
+        if block.block_type == BLOCK_TYPE_FIELD {
+            row.clear();
+            for field in header.fields.iter() {
+                let value = (block.records.get(&field.name).unwrap() + field.transform as f64)
+                    * field.scale as f64;
+
+                if field.display_style == FIELD_DISPLAY_STYLE_FLOAT {
+                    row.push(format!("{:.1$}", value, field.digits as usize));
+                } else {
+                    row.push(value.to_string());
+                }
+            }
+            writer.write_record(&row)?;
+        }
+
+        count += 1;
+        if count % PROGRESS_INTERVAL == 0 {
+            println!("{}: {} blocks processed", path.display(), count);
+        }
     }
 
-    Ok(result)
+    writer.flush()?;
+    println!("Generated: {}", path.display());
+
+    Ok(())
 }
 
-fn save_csv(parsed: &Parsed, path: &PathBuf) {
-    let line_writer = LineWriter::new(File::create(path).unwrap());
+fn stream_json(
+    header: &Header,
+    r: &mut Reader,
+    path: &PathBuf,
+    compress: bool,
+) -> Result<(), ParseError> {
+    let mut w = std::io::BufWriter::new(OutputWriter::new(File::create(path)?, compress));
+
+    write!(w, "{{")?;
+    write!(
+        w,
+        "\"fileFormat\":{},",
+        serde_json::to_string(&header.file_format)?
+    )?;
+    write!(w, "\"formatVersion\":{},", header.format_version)?;
+    write!(w, "\"timestamp\":{},", header.timestamp)?;
+    write!(w, "\"infoDataStart\":{},", header.info_data_start)?;
+    write!(w, "\"dataBeginIndex\":{},", header.data_begin_index)?;
+    write!(w, "\"recordLength\":{},", header.record_length)?;
+    write!(w, "\"numLoggerFields\":{},", header.num_logger_fields)?;
+    write!(w, "\"fields\":{},", serde_json::to_string(&header.fields)?)?;
+    write!(
+        w,
+        "\"bitFieldNames\":{},",
+        serde_json::to_string(&header.bit_field_names)?
+    )?;
+    write!(
+        w,
+        "\"infoData\":{},",
+        serde_json::to_string(&header.info_data)?
+    )?;
+    write!(w, "\"dataBlocks\":[")?;
+
+    let mut count = 0usize;
+    while r.remaining() > 0 {
+        let block = parse_data_block(r, &header.fields)?;
+
+        if count > 0 {
+            write!(w, ",")?;
+        }
+        serde_json::to_writer(&mut w, &block)?;
+
+        count += 1;
+        if count % PROGRESS_INTERVAL == 0 {
+            println!("{}: {} blocks processed", path.display(), count);
+        }
+    }
+
+    write!(w, "]}}")?;
+    w.flush()?;
+    println!("Generated: {}", path.display());
+
+    Ok(())
+}
+
+fn save_csv(parsed: &Parsed, path: &PathBuf, compress: bool) {
+    let line_writer = LineWriter::new(OutputWriter::new(File::create(path).unwrap(), compress));
     let mut writer = WriterBuilder::new()
         .delimiter(b'\t')
         .from_writer(line_writer);
@@ -322,78 +711,3 @@ fn save_csv(parsed: &Parsed, path: &PathBuf) {
 
     writer.flush().unwrap();
 }
-
-fn advance(offset: &mut usize, length: usize) {
-    *offset += length;
-}
-
-fn jump(offset: &mut usize, to: usize) {
-    *offset = to;
-}
-
-fn parse_string(buff: &[u8], offset: &mut usize, length: usize) -> String {
-    let val = str::from_utf8(&buff[*offset..(*offset + length)])
-        .expect("Unable to parse string")
-        .trim_matches(char::from(0))
-        .to_string();
-    advance(offset, length);
-    val
-}
-
-fn parse_i8(buff: &[u8], offset: &mut usize) -> i8 {
-    let length = std::mem::size_of::<i8>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    i8::from_be_bytes([buff[0]])
-}
-
-fn parse_u8(buff: &[u8], offset: &mut usize) -> u8 {
-    let length = std::mem::size_of::<u8>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    u8::from_be_bytes([buff[0]])
-}
-
-fn parse_i16(buff: &[u8], offset: &mut usize) -> i16 {
-    let length = std::mem::size_of::<i16>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    i16::from_be_bytes([buff[0], buff[1]])
-}
-
-fn parse_u16(buff: &[u8], offset: &mut usize) -> u16 {
-    let length = std::mem::size_of::<u16>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    u16::from_be_bytes([buff[0], buff[1]])
-}
-
-fn parse_i32(buff: &[u8], offset: &mut usize) -> i32 {
-    let length = std::mem::size_of::<i32>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    i32::from_be_bytes([buff[0], buff[1], buff[2], buff[3]])
-}
-
-fn parse_u32(buff: &[u8], offset: &mut usize) -> u32 {
-    let length = std::mem::size_of::<u32>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    u32::from_be_bytes([buff[0], buff[1], buff[2], buff[3]])
-}
-
-fn parse_f32(buff: &[u8], offset: &mut usize) -> f32 {
-    let length = std::mem::size_of::<f32>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    f32::from_be_bytes([buff[0], buff[1], buff[2], buff[3]])
-}
-
-fn parse_i64(buff: &[u8], offset: &mut usize) -> i64 {
-    let length = std::mem::size_of::<i64>();
-    let buff = &buff[*offset..(*offset + length)];
-    advance(offset, length);
-    i64::from_be_bytes([
-        buff[0], buff[1], buff[2], buff[3], buff[4], buff[5], buff[6], buff[7],
-    ])
-}
@@ -0,0 +1,127 @@
+use crate::error::ParseError;
+use std::str;
+
+/// A cursor over a byte buffer with bounds-checked, big-endian reads.
+///
+/// This replaces the old pattern of threading a `&mut usize` offset through a
+/// dozen near-identical `parse_*` functions: every read goes through `take`,
+/// so out-of-range access is a single `ParseError::UnexpectedEof` instead of
+/// a panicking slice index.
+pub(crate) struct Reader<'a> {
+    buff: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buff: &'a [u8]) -> Self {
+        Reader { buff, pos: 0 }
+    }
+
+    /// Bytes left to read.
+    pub(crate) fn remaining(&self) -> usize {
+        self.buff.len().saturating_sub(self.pos)
+    }
+
+    /// Current absolute position, e.g. to compute a section length from two
+    /// header offsets.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Jumps to an absolute position, e.g. to skip over a variable-length
+    /// blob whose length is only known from a header field.
+    pub(crate) fn seek(&mut self, to: usize) {
+        self.pos = to;
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        if len > self.buff.len().saturating_sub(self.pos) {
+            return Err(ParseError::UnexpectedEof {
+                offset: self.pos,
+                needed: len,
+            });
+        }
+        let slice = &self.buff[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    /// Reads a fixed-width, NUL-padded string.
+    pub(crate) fn read_str(&mut self, len: usize) -> Result<String, ParseError> {
+        let start = self.pos;
+        let slice = self.take(len)?;
+        let val = str::from_utf8(slice)
+            .map_err(|_| ParseError::BadUtf8 { offset: start })?
+            .trim_matches(char::from(0))
+            .to_string();
+        Ok(val)
+    }
+
+    pub(crate) fn read_i8(&mut self) -> Result<i8, ParseError> {
+        Ok(i8::from_be_bytes([self.take(1)?[0]]))
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, ParseError> {
+        Ok(u8::from_be_bytes([self.take(1)?[0]]))
+    }
+
+    pub(crate) fn read_i16(&mut self) -> Result<i16, ParseError> {
+        let b = self.take(2)?;
+        Ok(i16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub(crate) fn read_u16(&mut self) -> Result<u16, ParseError> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub(crate) fn read_i32(&mut self) -> Result<i32, ParseError> {
+        let b = self.take(4)?;
+        Ok(i32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, ParseError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub(crate) fn read_f32(&mut self) -> Result<f32, ParseError> {
+        let b = self.take(4)?;
+        Ok(f32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub(crate) fn read_i64(&mut self) -> Result<i64, ParseError> {
+        let b = self.take(8)?;
+        Ok(i64::from_be_bytes([
+            b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+        ]))
+    }
+
+    /// Reads any type with a `FromReader` impl, e.g. `r.read::<i32>()?`.
+    pub(crate) fn read<T: FromReader>(&mut self) -> Result<T, ParseError> {
+        T::from_reader(self)
+    }
+}
+
+pub(crate) trait FromReader: Sized {
+    fn from_reader(r: &mut Reader) -> Result<Self, ParseError>;
+}
+
+macro_rules! impl_from_reader {
+    ($ty:ty, $method:ident) => {
+        impl FromReader for $ty {
+            fn from_reader(r: &mut Reader) -> Result<Self, ParseError> {
+                r.$method()
+            }
+        }
+    };
+}
+
+impl_from_reader!(i8, read_i8);
+impl_from_reader!(u8, read_u8);
+impl_from_reader!(i16, read_i16);
+impl_from_reader!(u16, read_u16);
+impl_from_reader!(i32, read_i32);
+impl_from_reader!(u32, read_u32);
+impl_from_reader!(f32, read_f32);
+impl_from_reader!(i64, read_i64);
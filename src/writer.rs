@@ -0,0 +1,153 @@
+use crate::error::ParseError;
+use crate::parser::{
+    DataBlock, LoggerFieldScalar, Parsed, BLOCK_TYPE_FIELD, BLOCK_TYPE_MARKER, FIELD_NAME_LENGTH,
+    FIELD_UNITS_LENGTH, FORMAT_LENGTH, MARKER_MESSAGE_LENGTH,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+/// The write-side counterpart of `FromReader`: encodes a value back into the
+/// exact big-endian layout the parser expects.
+pub(crate) trait ToWriter {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), ParseError>;
+}
+
+/// Writes `s` into `width` bytes, NUL-padding (or truncating) it to the fixed
+/// field width the reader expects.
+fn write_padded<W: Write>(w: &mut W, s: &str, width: usize) -> Result<(), ParseError> {
+    let mut buf = vec![0u8; width];
+    let bytes = s.as_bytes();
+    let n = bytes.len().min(width);
+    buf[..n].copy_from_slice(&bytes[..n]);
+    w.write_all(&buf)?;
+    Ok(())
+}
+
+/// Inverse of the `display_style` match in `parse_header`.
+fn display_style_code(style: &str) -> Result<i8, ParseError> {
+    Ok(match style {
+        "Float" => 0,
+        "Hex" => 1,
+        "bits" => 2,
+        "Date" => 3,
+        "On/Off" => 4,
+        "Yes/No" => 5,
+        "High/Low" => 6,
+        "Active/Inactive" => 7,
+        other => {
+            return Err(ParseError::msg(format!(
+                "unsupported field display style: {other}"
+            )))
+        }
+    })
+}
+
+impl ToWriter for LoggerFieldScalar {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), ParseError> {
+        w.write_all(&self.field_type.to_be_bytes())?;
+        write_padded(w, &self.name, FIELD_NAME_LENGTH)?;
+        write_padded(w, &self.units, FIELD_UNITS_LENGTH)?;
+        w.write_all(&display_style_code(&self.display_style)?.to_be_bytes())?;
+        w.write_all(&self.scale.to_be_bytes())?;
+        w.write_all(&self.transform.to_be_bytes())?;
+        w.write_all(&self.digits.to_be_bytes())?;
+        Ok(())
+    }
+}
+
+/// Encodes a single logger field record per the field's `field_type`, the
+/// inverse of the scalar-width match in `parse_data_block`.
+fn write_record<W: Write>(w: &mut W, field_type: i8, value: f64) -> Result<(), ParseError> {
+    match field_type {
+        0 | 10 => w.write_all(&(value as u8).to_be_bytes())?,
+        1 => w.write_all(&(value as i8).to_be_bytes())?,
+        2 | 11 => w.write_all(&(value as u16).to_be_bytes())?,
+        3 => w.write_all(&(value as i16).to_be_bytes())?,
+        4 | 12 => w.write_all(&(value as u32).to_be_bytes())?,
+        5 => w.write_all(&(value as i32).to_be_bytes())?,
+        6 => w.write_all(&(value as i64).to_be_bytes())?,
+        7 => w.write_all(&(value as f32).to_be_bytes())?,
+        other => return Err(ParseError::UnsupportedFieldType(other)),
+    }
+    Ok(())
+}
+
+/// Writes a single data block. Field-type blocks need the logger field list
+/// to know the byte width and order of each record, so unlike
+/// `LoggerFieldScalar` this isn't a bare `ToWriter` impl.
+fn write_data_block<W: Write>(
+    block: &DataBlock,
+    fields: &[LoggerFieldScalar],
+    w: &mut W,
+) -> Result<(), ParseError> {
+    w.write_all(&block.block_type.to_be_bytes())?;
+    w.write_all(&block.counter.to_be_bytes())?;
+    w.write_all(&block.timestamp.to_be_bytes())?;
+
+    match block.block_type {
+        BLOCK_TYPE_FIELD => {
+            for field in fields {
+                let value = block.records.get(&field.name).ok_or_else(|| {
+                    ParseError::msg(format!("missing record for field `{}`", field.name))
+                })?;
+                write_record(w, field.field_type, *value)?;
+            }
+            // crc byte the reader currently skips, see `parse_data_block`
+            w.write_all(&[0u8])?;
+        }
+        BLOCK_TYPE_MARKER => write_padded(w, &block.message, MARKER_MESSAGE_LENGTH)?,
+        other => return Err(ParseError::UnsupportedBlockType(other)),
+    }
+
+    Ok(())
+}
+
+impl ToWriter for Parsed {
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<(), ParseError> {
+        let mut fields_buf = Vec::new();
+        for field in &self.fields {
+            field.write_to(&mut fields_buf)?;
+        }
+
+        let bit_field_names = self.bit_field_names.as_bytes();
+        let info_data = self.info_data.as_bytes();
+
+        // format string + format_version + timestamp + info_data_start +
+        // data_begin_index + record_length + num_logger_fields
+        const HEADER_LENGTH: usize = FORMAT_LENGTH + 2 + 4 + 2 + 4 + 2 + 2;
+        let logger_fields_length = HEADER_LENGTH + fields_buf.len();
+        let info_data_start = logger_fields_length + bit_field_names.len();
+        let data_begin_index = info_data_start + info_data.len();
+
+        write_padded(w, &self.file_format, FORMAT_LENGTH)?;
+        w.write_all(&self.format_version.to_be_bytes())?;
+        w.write_all(&self.timestamp.to_be_bytes())?;
+        w.write_all(&(info_data_start as i16).to_be_bytes())?;
+        w.write_all(&(data_begin_index as i32).to_be_bytes())?;
+        w.write_all(&self.record_length.to_be_bytes())?;
+        w.write_all(&(self.fields.len() as i16).to_be_bytes())?;
+
+        w.write_all(&fields_buf)?;
+        w.write_all(bit_field_names)?;
+        w.write_all(info_data)?;
+
+        for block in &self.data_blocks {
+            write_data_block(block, &self.fields, w)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reconstructs a `.mlg` file from a previously parsed (e.g. JSON-decoded)
+/// `Parsed` value.
+pub(crate) fn save_mlg(parsed: &Parsed, path: &PathBuf) -> Result<(), ParseError> {
+    let file = File::create(path).map_err(|e| ParseError::from(e).context("creating file"))?;
+    let mut writer = BufWriter::new(file);
+    parsed.write_to(&mut writer)?;
+    writer.flush()?;
+    Ok(())
+}